@@ -2,10 +2,12 @@
 Blockchain Monitoring System
 """
 
+import os
 import time
 import logging
+import sqlite3
 import schedule
-from wallet_manager import WalletManager
+from wallet_manager import WalletManager, DB_PATH
 from datetime import datetime, timedelta
 
 # Configure logging
@@ -17,8 +19,13 @@ logging.basicConfig(
 logger = logging.getLogger(__name__)
 
 class BlockchainMonitor:
-    def __init__(self):
-        self.wallet_manager = WalletManager()
+    def __init__(self, db_path=DB_PATH, convert_auto_vacuum=False, size_quota_mb=0):
+        self.wallet_manager = WalletManager(db_path)
+        # One-time full VACUUM to enable incremental vacuum (opt-in)
+        self.convert_auto_vacuum = convert_auto_vacuum
+        # Database size quota in MB (0 disables the check)
+        self.size_quota_mb = size_quota_mb
+        self.last_storage_sample = None
         
     def verify_chain_integrity(self):
         """Verify blockchain integrity"""
@@ -164,18 +171,165 @@ class BlockchainMonitor:
             
         # Recalculate balances
         self.wallet_manager.recalculate_all_balances()
-        
+
+    def collect_storage_stats(self):
+        """Collect database size, rows per table and growth rate"""
+        db_path = self.wallet_manager.db_path
+        size_bytes = sum(
+            os.path.getsize(db_path + suffix)
+            for suffix in ("", "-wal")
+            if os.path.exists(db_path + suffix)
+        )
+
+        conn = self.wallet_manager.get_connection()
+        cursor = conn.cursor()
+
+        try:
+            cursor.execute("""
+                SELECT name FROM sqlite_master
+                WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+                ORDER BY name
+            """)
+            tables = [row[0] for row in cursor.fetchall()]
+
+            rows = {}
+            for table in tables:
+                cursor.execute(f'SELECT COUNT(*) FROM "{table}"')
+                rows[table] = cursor.fetchone()[0]
+
+        finally:
+            cursor.close()
+            conn.close()
+
+        # Growth rate since the previous sample, in bytes per day
+        now = time.time()
+        growth_per_day = None
+        if self.last_storage_sample:
+            last_time, last_size = self.last_storage_sample
+            if now > last_time:
+                growth_per_day = (size_bytes - last_size) * 86400 / (now - last_time)
+        self.last_storage_sample = (now, size_bytes)
+
+        return {
+            'size_bytes': size_bytes,
+            'rows': rows,
+            'growth_bytes_per_day': growth_per_day
+        }
+
+    def storage_task(self):
+        """Report storage usage and check the size quota"""
+        stats = self.collect_storage_stats()
+        size_mb = stats['size_bytes'] / (1024 * 1024)
+
+        logger.info(f"Tamanho do banco: {size_mb:.2f} MB")
+        for table, count in stats['rows'].items():
+            logger.info(f"  - {table}: {count} registros")
+        if stats['growth_bytes_per_day'] is not None:
+            growth_mb = stats['growth_bytes_per_day'] / (1024 * 1024)
+            logger.info(f"Crescimento do banco: {growth_mb:.2f} MB/dia")
+
+        stats['quota_exceeded'] = False
+        if self.size_quota_mb > 0:
+            usage = size_mb / self.size_quota_mb
+            if usage >= 1:
+                stats['quota_exceeded'] = True
+                logger.error(
+                    f"Cota de armazenamento excedida: {size_mb:.2f}/{self.size_quota_mb} MB"
+                )
+            elif usage >= 0.9:
+                logger.warning(
+                    f"Banco próximo da cota de armazenamento: {size_mb:.2f}/{self.size_quota_mb} MB"
+                )
+
+        return stats
+
+    def maintenance_task(self):
+        """Run SQLite maintenance (integrity check, WAL checkpoint, incremental vacuum)"""
+        logger.info("Iniciando manutenção do banco de dados...")
+
+        conn = self.wallet_manager.get_connection()
+        cursor = conn.cursor()
+
+        try:
+            # Verify database file integrity
+            cursor.execute("PRAGMA integrity_check")
+            results = [row[0] for row in cursor.fetchall()]
+            if results != ["ok"]:
+                logger.error("Falha na verificação de integridade do banco:")
+                for result in results:
+                    logger.error(f"  - {result}")
+                return False
+            logger.info("Integridade do banco verificada")
+
+            # Fold the WAL back into the main file (no-op outside WAL mode)
+            cursor.execute("PRAGMA wal_checkpoint(TRUNCATE)")
+            busy, log_frames, checkpointed = cursor.fetchone()
+            if log_frames == -1:
+                logger.info("Banco fora do modo WAL, checkpoint ignorado")
+            elif busy:
+                logger.warning("Checkpoint do WAL incompleto: banco em uso")
+            else:
+                logger.info(f"Checkpoint do WAL concluído ({checkpointed}/{log_frames} páginas)")
+
+            # Incremental vacuum needs auto_vacuum=INCREMENTAL, which only
+            # takes effect after one full VACUUM on an existing database
+            cursor.execute("PRAGMA auto_vacuum")
+            if cursor.fetchone()[0] != 2:
+                if not self.convert_auto_vacuum:
+                    logger.info(
+                        "Vacuum incremental indisponível (auto_vacuum desativado); "
+                        "defina DB_MAINTENANCE_CONVERT_AUTO_VACUUM=1 para converter"
+                    )
+                    return True
+
+                logger.info("Ativando auto_vacuum incremental (VACUUM completo)...")
+                try:
+                    cursor.execute("PRAGMA auto_vacuum = INCREMENTAL")
+                    conn.execute("VACUUM")
+                except sqlite3.Error as e:
+                    # Never retry a blocking full VACUUM unattended
+                    self.convert_auto_vacuum = False
+                    logger.error(
+                        f"Falha ao ativar auto_vacuum incremental: {e}; "
+                        "conversão desativada até o próximo reinício"
+                    )
+                    return False
+                logger.info("auto_vacuum incremental ativado")
+            else:
+                cursor.execute("PRAGMA freelist_count")
+                free_pages = cursor.fetchone()[0]
+                cursor.execute("PRAGMA incremental_vacuum")
+                cursor.fetchall()
+                logger.info(f"Vacuum incremental concluído ({free_pages} páginas liberadas)")
+
+            return True
+
+        except Exception as e:
+            logger.error(f"Erro na manutenção do banco: {e}")
+            return False
+
+        finally:
+            cursor.close()
+            conn.close()
+
 def main():
     """Main function"""
-    monitor = BlockchainMonitor()
+    monitor = BlockchainMonitor(
+        convert_auto_vacuum=os.getenv("DB_MAINTENANCE_CONVERT_AUTO_VACUUM", "0") == "1",
+        size_quota_mb=float(os.getenv("DB_SIZE_QUOTA_MB", "0"))
+    )
     
     # Schedule tasks
     schedule.every(10).minutes.do(monitor.monitor_task)
     schedule.every().day.at("00:00").do(monitor.generate_daily_report)
-    
+    maintenance_time = os.getenv("DB_MAINTENANCE_TIME", "03:00")
+    schedule.every().day.at(maintenance_time).do(monitor.maintenance_task)
+    schedule.every().hour.do(monitor.storage_task)
+
     # Run initial check
     monitor.monitor_task()
     monitor.generate_daily_report()
+    monitor.storage_task()
     
     # Keep running
     while True:
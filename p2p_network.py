@@ -64,14 +64,19 @@ class P2PNetwork:
         self.block_processor = None
         self.tx_processor = None
         
-    def start(self):
-        """Start P2P network"""
+    def start(self, listen_socket: Optional[socket.socket] = None):
+        """Start P2P network, optionally on an already listening socket"""
         try:
-            # Initialize server socket
-            self.server_socket = socket.socket(socket.AF_INET, socket.SOCK_STREAM)
-            self.server_socket.setsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR, 1)
-            self.server_socket.bind((self.host, self.port))
-            self.server_socket.listen(10)
+            if listen_socket:
+                # Inherited socket (e.g. systemd socket activation)
+                self.server_socket = listen_socket
+                self.host, self.port = self.server_socket.getsockname()[:2]
+            else:
+                # Initialize server socket
+                self.server_socket = socket.socket(socket.AF_INET, socket.SOCK_STREAM)
+                self.server_socket.setsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR, 1)
+                self.server_socket.bind((self.host, self.port))
+                self.server_socket.listen(10)
             
             self.running = True
             
@@ -1,151 +1,273 @@
-"""
-Node startup script with database persistence
-"""
-
-import os
-import sys
-import time
-import logging
-import threading
-import argparse
-from typing import Optional
-from pathlib import Path
-
-from p2p_network import P2PNetwork
-from mining_manager import MiningManager
-from database_manager import DatabaseManager
-
-# Configure logging
-logging.basicConfig(
-    level=logging.INFO,
-    format='%(asctime)s - %(levelname)s - %(message)s'
-)
-logger = logging.getLogger(__name__)
-
-class Node:
-    """Blockchain node with persistence"""
-    
-    def __init__(
-        self,
-        host: str = "localhost",
-        port: int = 5000,
-        miner_address: Optional[str] = None,
-        is_miner: bool = False,
-        difficulty: int = 4
-    ):
-        self.host = host
-        self.port = port
-        self.is_miner = is_miner
-        self.miner_address = miner_address
-        self.difficulty = difficulty
-        
-        # Initialize components
-        self.db = DatabaseManager()
-        self.network = P2PNetwork(host, port)
-        
-        if self.is_miner and self.miner_address:
-            self.mining_manager = MiningManager(
-                miner_address=self.miner_address,
-                difficulty=self.difficulty
-            )
-        else:
-            self.mining_manager = None
-            
-        # Control flags
-        self.stop_mining = threading.Event()
-        
-    def start(self):
-        """Start node operation"""
-        try:
-            # Ensure data directory exists
-            os.makedirs("data/blockchain", exist_ok=True)
-            
-            # Start P2P network
-            logger.info(f"Starting P2P network on {self.host}:{self.port}")
-            self.network.start()
-            
-            # Start mining if enabled
-            if self.is_miner and self.mining_manager:
-                logger.info("Starting mining operations...")
-                mining_thread = threading.Thread(
-                    target=self.mining_manager.start_mining,
-                    args=(self.stop_mining,)
-                )
-                mining_thread.daemon = True
-                mining_thread.start()
-                
-            # Keep main thread alive
-            try:
-                while True:
-                    time.sleep(1)
-            except KeyboardInterrupt:
-                self.stop()
-                
-        except Exception as e:
-            logger.error(f"Node startup error: {str(e)}")
-            self.stop()
-            
-    def stop(self):
-        """Stop node operation"""
-        logger.info("Stopping node...")
-        
-        if self.is_miner:
-            self.stop_mining.set()
-            
-        self.network.stop()
-        
-def main():
-    parser = argparse.ArgumentParser(description="Start a blockchain node")
-    
-    parser.add_argument(
-        "--host",
-        type=str,
-        default="localhost",
-        help="Host address to bind to"
-    )
-    
-    parser.add_argument(
-        "--port",
-        type=int,
-        default=5000,
-        help="Port to listen on"
-    )
-    
-    parser.add_argument(
-        "--miner",
-        action="store_true",
-        help="Run as a mining node"
-    )
-    
-    parser.add_argument(
-        "--miner-address",
-        type=str,
-        help="Miner's wallet address"
-    )
-    
-    parser.add_argument(
-        "--difficulty",
-        type=int,
-        default=4,
-        help="Mining difficulty"
-    )
-    
-    args = parser.parse_args()
-    
-    # Validate miner configuration
-    if args.miner and not args.miner_address:
-        parser.error("Miner address is required for mining nodes")
-        
-    # Start node
-    node = Node(
-        host=args.host,
-        port=args.port,
-        miner_address=args.miner_address,
-        is_miner=args.miner,
-        difficulty=args.difficulty
-    )
-    
-    node.start()
-    
-if __name__ == "__main__":
+"""
+Node startup script with database persistence
+"""
+
+import os
+import sys
+import time
+import signal
+import socket
+import logging
+import threading
+import argparse
+from typing import Optional
+from pathlib import Path
+
+from p2p_network import P2PNetwork
+from mining_manager import MiningManager
+from database_manager import DatabaseManager
+
+# Configure logging
+logging.basicConfig(
+    level=logging.INFO,
+    format='%(asctime)s - %(levelname)s - %(message)s'
+)
+logger = logging.getLogger(__name__)
+
+def sd_notify(state: str):
+    """Send a state update to systemd if running under Type=notify"""
+    address = os.environ.get("NOTIFY_SOCKET")
+    if not address:
+        return
+    if address.startswith("@"):
+        address = "\0" + address[1:]
+        
+    try:
+        with socket.socket(socket.AF_UNIX, socket.SOCK_DGRAM) as sock:
+            sock.sendto(state.encode(), address)
+    except OSError as e:
+        logger.warning(f"sd_notify failed: {str(e)}")
+        
+def systemd_watchdog_interval() -> Optional[float]:
+    """Watchdog interval in seconds requested by systemd, if any"""
+    usec = os.environ.get("WATCHDOG_USEC")
+    pid = os.environ.get("WATCHDOG_PID")
+    if not usec or (pid and pid != str(os.getpid())):
+        return None
+    return int(usec) / 1_000_000
+    
+def systemd_listen_socket() -> Optional[socket.socket]:
+    """Listening socket passed by systemd socket activation, if any"""
+    if os.environ.get("LISTEN_PID") != str(os.getpid()):
+        return None
+    if int(os.environ.get("LISTEN_FDS", "0")) < 1:
+        return None
+        
+    # First passed descriptor is always fd 3 (SD_LISTEN_FDS_START)
+    for name in ("LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"):
+        os.environ.pop(name, None)
+    return socket.socket(fileno=3)
+    
+class Node:
+    """Blockchain node with persistence"""
+    
+    def __init__(
+        self,
+        host: str = "localhost",
+        port: int = 5000,
+        miner_address: Optional[str] = None,
+        is_miner: bool = False,
+        difficulty: int = 4,
+        listen_socket: Optional[socket.socket] = None
+    ):
+        self.host = host
+        self.port = port
+        self.is_miner = is_miner
+        self.miner_address = miner_address
+        self.difficulty = difficulty
+        self.listen_socket = listen_socket
+        
+        # Initialize components
+        self.db = DatabaseManager()
+        self.network = P2PNetwork(host, port)
+        
+        if self.is_miner and self.miner_address:
+            self.mining_manager = MiningManager(
+                miner_address=self.miner_address,
+                difficulty=self.difficulty
+            )
+        else:
+            self.mining_manager = None
+            
+        # Control flags
+        self.stop_mining = threading.Event()
+        self.stopped = threading.Event()
+        
+    def start(self):
+        """Start node operation"""
+        try:
+            # Ensure data directory exists
+            os.makedirs("data/blockchain", exist_ok=True)
+            
+            # Start P2P network
+            if self.listen_socket:
+                logger.info("Starting P2P network on inherited socket")
+            else:
+                logger.info(f"Starting P2P network on {self.host}:{self.port}")
+            self.network.start(self.listen_socket)
+            if not self.network.running:
+                raise RuntimeError("P2P network failed to start")
+            
+            # Start mining if enabled
+            if self.is_miner and self.mining_manager:
+                logger.info("Starting mining operations...")
+                mining_thread = threading.Thread(
+                    target=self.mining_manager.start_mining,
+                    args=(self.stop_mining,)
+                )
+                mining_thread.daemon = True
+                mining_thread.start()
+                
+            sd_notify("READY=1")
+            
+            # Keep main thread alive until stopped, feeding the
+            # systemd watchdog at half its interval when enabled
+            watchdog_interval = systemd_watchdog_interval()
+            last_ping = time.time()
+            try:
+                while not self.stopped.is_set():
+                    time.sleep(1)
+                    if watchdog_interval and time.time() - last_ping >= watchdog_interval / 2:
+                        sd_notify("WATCHDOG=1")
+                        last_ping = time.time()
+            except KeyboardInterrupt:
+                self.stop()
+                
+        except Exception as e:
+            logger.error(f"Node startup error: {str(e)}")
+            self.stop()
+            
+    def stop(self):
+        """Stop node operation"""
+        if self.stopped.is_set():
+            return
+        self.stopped.set()
+        
+        logger.info("Stopping node...")
+        sd_notify("STOPPING=1")
+        
+        if self.is_miner:
+            self.stop_mining.set()
+            
+        self.network.stop()
+        
+def daemonize():
+    """Detach from the terminal (double fork)"""
+    if os.fork() > 0:
+        os._exit(0)
+    os.setsid()
+    if os.fork() > 0:
+        os._exit(0)
+        
+    # Working directory is kept: data paths are relative to it
+    sys.stdout.flush()
+    sys.stderr.flush()
+    devnull = os.open(os.devnull, os.O_RDWR)
+    for fd in (0, 1, 2):
+        os.dup2(devnull, fd)
+    os.close(devnull)
+    
+def write_pid_file(pid_file: str):
+    """Write the current process id to the PID file"""
+    with open(pid_file, "w") as f:
+        f.write(f"{os.getpid()}\n")
+        
+def remove_pid_file(pid_file: str):
+    """Remove the PID file if it belongs to this process"""
+    try:
+        with open(pid_file) as f:
+            if int(f.read().strip()) != os.getpid():
+                return
+        os.remove(pid_file)
+    except (OSError, ValueError):
+        pass
+        
+def install_signal_handlers(node: Node):
+    """Stop the node gracefully on SIGTERM and SIGHUP"""
+    def handle_signal(signum, frame):
+        logger.info(f"Received {signal.Signals(signum).name}")
+        node.stop()
+        
+    signal.signal(signal.SIGTERM, handle_signal)
+    signal.signal(signal.SIGHUP, handle_signal)
+    
+def main():
+    parser = argparse.ArgumentParser(description="Start a blockchain node")
+    
+    parser.add_argument(
+        "--host",
+        type=str,
+        default="localhost",
+        help="Host address to bind to"
+    )
+    
+    parser.add_argument(
+        "--port",
+        type=int,
+        default=5000,
+        help="Port to listen on"
+    )
+    
+    parser.add_argument(
+        "--miner",
+        action="store_true",
+        help="Run as a mining node"
+    )
+    
+    parser.add_argument(
+        "--miner-address",
+        type=str,
+        help="Miner's wallet address"
+    )
+    
+    parser.add_argument(
+        "--difficulty",
+        type=int,
+        default=4,
+        help="Mining difficulty"
+    )
+    
+    parser.add_argument(
+        "--daemon",
+        action="store_true",
+        help="Detach and run in the background (logs go to /dev/null); "
+             "do not combine with systemd Type=notify or socket activation"
+    )
+    
+    parser.add_argument(
+        "--pid-file",
+        type=str,
+        help="Write the node's process id to this file"
+    )
+    
+    args = parser.parse_args()
+    
+    # Validate miner configuration
+    if args.miner and not args.miner_address:
+        parser.error("Miner address is required for mining nodes")
+        
+    if args.daemon:
+        daemonize()
+        
+    if args.pid_file:
+        write_pid_file(args.pid_file)
+        
+    try:
+        # Start node
+        node = Node(
+            host=args.host,
+            port=args.port,
+            miner_address=args.miner_address,
+            is_miner=args.miner,
+            difficulty=args.difficulty,
+            listen_socket=systemd_listen_socket()
+        )
+        
+        install_signal_handlers(node)
+        node.start()
+    finally:
+        if args.pid_file:
+            remove_pid_file(args.pid_file)
+    
+if __name__ == "__main__":
     main() 
\ No newline at end of file
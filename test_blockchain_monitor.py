@@ -0,0 +1,213 @@
+"""
+Test blockchain monitor database maintenance
+"""
+
+import os
+import sys
+import time
+import sqlite3
+import unittest
+from unittest import mock
+
+# The monitor logs to data/logs and only needs `schedule` in main()
+os.makedirs("data/logs", exist_ok=True)
+with mock.patch.dict(sys.modules, {"schedule": mock.MagicMock()}):
+    from blockchain_monitor import BlockchainMonitor
+
+class TestMaintenanceTask(unittest.TestCase):
+    """Test SQLite maintenance task"""
+
+    def setUp(self):
+        """Set up test environment"""
+        # Use test database
+        os.makedirs("data/blockchain", exist_ok=True)
+        self.test_db = "data/blockchain/test_monitor.db"
+        self.remove_db()
+
+    def tearDown(self):
+        """Clean up test environment"""
+        self.remove_db()
+
+    def remove_db(self):
+        """Remove test database and its WAL/journal files"""
+        for suffix in ("", "-wal", "-shm", "-journal"):
+            if os.path.exists(self.test_db + suffix):
+                os.remove(self.test_db + suffix)
+
+    def create_db(self, journal_mode: str):
+        """Create a test database with free pages left by deleted rows"""
+        conn = sqlite3.connect(self.test_db)
+        conn.execute(f"PRAGMA journal_mode = {journal_mode}")
+        conn.execute("CREATE TABLE blocks (hash TEXT PRIMARY KEY, data TEXT)")
+        conn.executemany(
+            "INSERT INTO blocks VALUES (?, ?)",
+            [(f"hash_{i}", "x" * 1000) for i in range(200)]
+        )
+        conn.commit()
+        conn.execute("DELETE FROM blocks")
+        conn.commit()
+        conn.close()
+
+    def pragma(self, name: str):
+        """Read a single PRAGMA value from the test database"""
+        conn = sqlite3.connect(self.test_db)
+        try:
+            return conn.execute(f"PRAGMA {name}").fetchone()[0]
+        finally:
+            conn.close()
+
+    def run_twice(self):
+        """Run maintenance twice: conversion run, then incremental run"""
+        monitor = BlockchainMonitor(self.test_db, convert_auto_vacuum=True)
+
+        self.assertTrue(monitor.maintenance_task(), "First maintenance run failed")
+        self.assertEqual(
+            self.pragma("auto_vacuum"),
+            2,
+            "auto_vacuum not switched to INCREMENTAL"
+        )
+
+        self.assertTrue(monitor.maintenance_task(), "Second maintenance run failed")
+        self.assertEqual(
+            self.pragma("freelist_count"),
+            0,
+            "Free pages left after incremental vacuum"
+        )
+
+    def test_maintenance_wal_mode(self):
+        """Test maintenance on a WAL database"""
+        self.create_db("WAL")
+
+        # Keep a connection open so SQLite does not remove the WAL on close
+        keeper = sqlite3.connect(self.test_db)
+        keeper.execute("INSERT INTO blocks VALUES ('pending', 'x')")
+        keeper.commit()
+        self.assertGreater(os.path.getsize(self.test_db + "-wal"), 0)
+
+        try:
+            self.run_twice()
+
+            self.assertEqual(self.pragma("journal_mode"), "wal", "Journal mode changed")
+            self.assertEqual(
+                os.path.getsize(self.test_db + "-wal"),
+                0,
+                "WAL not truncated by checkpoint"
+            )
+        finally:
+            keeper.close()
+
+    def test_maintenance_rollback_journal_mode(self):
+        """Test maintenance on a rollback-journal database"""
+        self.create_db("DELETE")
+
+        self.run_twice()
+
+        self.assertEqual(self.pragma("journal_mode"), "delete", "Journal mode changed")
+        self.assertFalse(
+            os.path.exists(self.test_db + "-wal"),
+            "WAL file created outside WAL mode"
+        )
+
+    def test_maintenance_without_conversion(self):
+        """Test maintenance leaves auto_vacuum alone unless opted in"""
+        self.create_db("DELETE")
+
+        monitor = BlockchainMonitor(self.test_db)
+
+        self.assertTrue(monitor.maintenance_task(), "Maintenance run failed")
+        self.assertEqual(self.pragma("auto_vacuum"), 0, "Database converted without opt-in")
+
+    def test_failed_conversion_not_retried(self):
+        """Test a failed full VACUUM is not retried on the next run"""
+        self.create_db("DELETE")
+
+        monitor = BlockchainMonitor(self.test_db, convert_auto_vacuum=True)
+        monitor.wallet_manager.get_connection = lambda: sqlite3.connect(self.test_db, timeout=0)
+
+        # Hold a write lock as a running node or miner would
+        writer = sqlite3.connect(self.test_db)
+        writer.execute("BEGIN IMMEDIATE")
+        try:
+            self.assertFalse(monitor.maintenance_task(), "VACUUM succeeded while locked")
+            self.assertFalse(monitor.convert_auto_vacuum, "Conversion still enabled after failure")
+
+            self.assertTrue(monitor.maintenance_task(), "Second run retried the VACUUM")
+        finally:
+            writer.rollback()
+            writer.close()
+
+        self.assertEqual(self.pragma("auto_vacuum"), 0, "Database converted after failure")
+
+    def test_maintenance_reports_corruption(self):
+        """Test maintenance fails on a corrupted database"""
+        self.create_db("DELETE")
+
+        # Overwrite the second page (table b-tree root) with garbage
+        with open(self.test_db, "r+b") as f:
+            f.seek(4096)
+            f.write(b"\xff" * 4096)
+
+        monitor = BlockchainMonitor.__new__(BlockchainMonitor)
+        monitor.wallet_manager = mock.MagicMock()
+        monitor.wallet_manager.get_connection = lambda: sqlite3.connect(self.test_db)
+
+        self.assertFalse(
+            monitor.maintenance_task(),
+            "Maintenance succeeded on a corrupted database"
+        )
+
+class TestStorageTask(unittest.TestCase):
+    """Test storage usage metrics and quota"""
+
+    def setUp(self):
+        """Set up test environment"""
+        # Use test database
+        os.makedirs("data/blockchain", exist_ok=True)
+        self.test_db = "data/blockchain/test_storage.db"
+        if os.path.exists(self.test_db):
+            os.remove(self.test_db)
+
+        conn = sqlite3.connect(self.test_db)
+        conn.execute("CREATE TABLE blocks (hash TEXT PRIMARY KEY, data TEXT)")
+        conn.execute("CREATE TABLE delivery_proofs (proof_hash TEXT PRIMARY KEY)")
+        conn.executemany(
+            "INSERT INTO blocks VALUES (?, ?)",
+            [(f"hash_{i}", "x" * 1000) for i in range(3)]
+        )
+        conn.commit()
+        conn.close()
+
+    def tearDown(self):
+        """Clean up test environment"""
+        if os.path.exists(self.test_db):
+            os.remove(self.test_db)
+
+    def test_storage_stats(self):
+        """Test size, rows per table and growth rate"""
+        monitor = BlockchainMonitor(self.test_db)
+
+        stats = monitor.storage_task()
+        self.assertEqual(stats['size_bytes'], os.path.getsize(self.test_db))
+        self.assertEqual(stats['rows']['blocks'], 3)
+        self.assertEqual(stats['rows']['delivery_proofs'], 0)
+        self.assertIn('wallets', stats['rows'])
+        self.assertIsNone(stats['growth_bytes_per_day'], "Growth reported without a previous sample")
+        self.assertFalse(stats['quota_exceeded'])
+
+        # Pretend the previous sample was a day ago and 1 KB smaller
+        monitor.last_storage_sample = (time.time() - 86400, stats['size_bytes'] - 1024)
+        stats = monitor.storage_task()
+        self.assertAlmostEqual(stats['growth_bytes_per_day'], 1024, delta=1)
+
+    def test_storage_quota(self):
+        """Test quota is reported once the database exceeds it"""
+        size_mb = os.path.getsize(self.test_db) / (1024 * 1024)
+
+        monitor = BlockchainMonitor(self.test_db, size_quota_mb=size_mb * 10)
+        self.assertFalse(monitor.storage_task()['quota_exceeded'], "Quota exceeded below limit")
+
+        monitor.size_quota_mb = size_mb / 2
+        self.assertTrue(monitor.storage_task()['quota_exceeded'], "Quota not exceeded above limit")
+
+if __name__ == "__main__":
+    unittest.main()